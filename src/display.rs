@@ -0,0 +1,110 @@
+//! SSD1306 OLED rendering for the live race countdown.
+//!
+//! Kept deliberately dumb: the `display` task hands over plain data
+//! (a state label and a remaining-seconds count) instead of the app's
+//! `State` type, so this module doesn't need to know anything about RTIC
+//! resources or the controller's scheduling.
+
+use core::fmt::Write as _;
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_10X20, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::{Baseline, Text},
+};
+use embedded_hal::blocking::i2c::Write as I2cWrite;
+use heapless::String;
+use ssd1306::{mode::BufferedGraphicsMode, prelude::*, I2CDisplayInterface, Ssd1306};
+
+/// The OLED driver type as brought up in `init`. Generic over the I2C
+/// implementation so the OLED can sit on a bus shared with other
+/// peripherals (see the `ds3231` RTC) via `shared-bus` proxies.
+pub type Oled<I2C> =
+    Ssd1306<I2CInterface<I2C>, DisplaySize128x64, BufferedGraphicsMode<DisplaySize128x64>>;
+
+/// Snapshot of what the countdown screen should show, handed over by the
+/// `display` task each refresh. `armed` is false while a sequence is
+/// still being picked, in which case `state`/`remaining_secs`/
+/// `warmup_secs` are ignored in favour of showing `sequence`.
+#[derive(Clone, Copy)]
+pub struct Countdown {
+    pub armed: bool,
+    pub sequence: &'static str,
+    pub state: &'static str,
+    pub remaining_secs: u32,
+    pub warmup_secs: Option<u64>,
+    /// Set once the warmup seed fell back to a fixed value because the
+    /// RTC entropy read failed, so the degraded randomness is visible on
+    /// the pier and not just in the defmt log.
+    pub rtc_fault: bool,
+    pub wall_clock: Option<(u8, u8, u8)>,
+}
+
+/// Brings up a freshly constructed SSD1306 in 128x64 mode, ready to draw.
+pub fn init<I2C: I2cWrite>(i2c: I2C) -> Oled<I2C> {
+    let interface = I2CDisplayInterface::new(i2c);
+    let mut oled = Ssd1306::new(interface, DisplaySize128x64, DisplayRotation::Rotate0)
+        .into_buffered_graphics_mode();
+    oled.init().ok();
+    oled
+}
+
+/// Redraws the countdown screen. While a sequence is still being picked
+/// (`!info.armed`) this shows the pending selection instead of a stale
+/// countdown; once armed it's the state name, a large MM:SS, and the
+/// warmup duration once it has been rolled. The time of day is shown
+/// either way.
+pub fn render<I2C: I2cWrite>(oled: &mut Oled<I2C>, info: Countdown) {
+    oled.clear(BinaryColor::Off).ok();
+
+    let style = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+
+    if info.armed {
+        Text::with_baseline(info.state, Point::new(0, 0), style, Baseline::Top)
+            .draw(oled)
+            .ok();
+
+        let mut clock: String<8> = String::new();
+        let _ = write!(
+            clock,
+            "{:02}:{:02}",
+            info.remaining_secs / 60,
+            info.remaining_secs % 60
+        );
+        Text::with_baseline(&clock, Point::new(0, 24), style, Baseline::Top)
+            .draw(oled)
+            .ok();
+
+        if let Some(warmup_secs) = info.warmup_secs {
+            let mut warmup: String<16> = String::new();
+            let _ = write!(warmup, "warmup {}s", warmup_secs);
+            Text::with_baseline(&warmup, Point::new(0, 48), style, Baseline::Top)
+                .draw(oled)
+                .ok();
+        }
+
+        if info.rtc_fault {
+            Text::with_baseline("RTC ERR", Point::new(64, 24), style, Baseline::Top)
+                .draw(oled)
+                .ok();
+        }
+    } else {
+        Text::with_baseline("Select:", Point::new(0, 0), style, Baseline::Top)
+            .draw(oled)
+            .ok();
+        Text::with_baseline(info.sequence, Point::new(0, 24), style, Baseline::Top)
+            .draw(oled)
+            .ok();
+    }
+
+    if let Some((hours, minutes, seconds)) = info.wall_clock {
+        let mut time_of_day: String<8> = String::new();
+        let _ = write!(time_of_day, "{:02}:{:02}:{:02}", hours, minutes, seconds);
+        Text::with_baseline(&time_of_day, Point::new(64, 48), style, Baseline::Top)
+            .draw(oled)
+            .ok();
+    }
+
+    oled.flush().ok();
+}
@@ -0,0 +1,66 @@
+//! Minimal DS3231 RTC driver: just enough to read wall-clock time and to
+//! mix a seed for the warmup RNG out of the seconds and temperature
+//! registers, which drift with ambient conditions and so are a little
+//! less predictable than a plain tick counter.
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+const ADDRESS: u8 = 0x68;
+
+const REG_SECONDS: u8 = 0x00;
+const REG_MINUTES: u8 = 0x01;
+const REG_HOURS: u8 = 0x02;
+const REG_TEMP_MSB: u8 = 0x11;
+const REG_TEMP_LSB: u8 = 0x12;
+
+/// Wall-clock time read back from the RTC, already converted from BCD.
+#[derive(Debug, Clone, Copy)]
+pub struct Time {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+}
+
+fn bcd_to_bin(bcd: u8) -> u8 {
+    (bcd & 0x0F) + (bcd >> 4) * 10
+}
+
+pub struct Ds3231<I2C> {
+    i2c: I2C,
+}
+
+impl<I2C, E> Ds3231<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    pub fn new(i2c: I2C) -> Self {
+        Self { i2c }
+    }
+
+    fn read_reg(&mut self, reg: u8) -> Result<u8, E> {
+        let mut buf = [0u8; 1];
+        self.i2c.write_read(ADDRESS, &[reg], &mut buf)?;
+        Ok(buf[0])
+    }
+
+    /// The current wall-clock time.
+    pub fn time(&mut self) -> Result<Time, E> {
+        Ok(Time {
+            seconds: bcd_to_bin(self.read_reg(REG_SECONDS)? & 0x7F),
+            minutes: bcd_to_bin(self.read_reg(REG_MINUTES)? & 0x7F),
+            hours: bcd_to_bin(self.read_reg(REG_HOURS)? & 0x3F),
+        })
+    }
+
+    /// A 64-bit seed mixing the raw (BCD) seconds register with the low
+    /// bits of the temperature register, so it changes even between
+    /// races started in the same second.
+    pub fn entropy_seed(&mut self) -> Result<u64, E> {
+        let seconds = self.read_reg(REG_SECONDS)?;
+        let temp_msb = self.read_reg(REG_TEMP_MSB)?;
+        let temp_lsb = self.read_reg(REG_TEMP_LSB)?;
+
+        let raw = (seconds as u64) << 16 | (temp_msb as u64) << 8 | (temp_lsb as u64);
+        Ok(raw.wrapping_mul(0x9E3779B97F4A7C15))
+    }
+}
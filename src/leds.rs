@@ -0,0 +1,58 @@
+//! WS2812 ("NeoPixel") strip driving for the flap lights.
+//!
+//! Callers hand over plain RGB8 pixel frames; this module doesn't know
+//! about the app's `Light`/`State` types.
+
+use embedded_hal::blocking::spi::Write as SpiWrite;
+use smart_leds::{SmartLedsWrite, RGB8};
+use ws2812_spi::Ws2812;
+
+/// Number of pixels on the strip, one per flap light it replaces.
+pub const PIXELS: usize = 3;
+
+/// How far (per channel, per frame) a crossfade is allowed to move,
+/// giving a "flap closing" feel instead of an instant snap.
+const STEP: i16 = 24;
+
+pub type Strip<SPI> = Ws2812<SPI>;
+
+/// Brings up the WS2812 driver over an SPI peripheral clocked for its
+/// bit-banged protocol (see `ws2812_spi::MODE`/timing requirements).
+pub fn init<SPI>(spi: SPI) -> Strip<SPI> {
+    Ws2812::new(spi)
+}
+
+/// Pushes a frame out to the strip. Errors are swallowed, same as the
+/// rest of the I/O in this app: a dropped frame just gets overwritten by
+/// the next animation tick ~20ms later.
+pub fn write<SPI, E>(strip: &mut Strip<SPI>, frame: &[RGB8; PIXELS])
+where
+    SPI: SpiWrite<u8, Error = E>,
+{
+    strip.write(frame.iter().copied()).ok();
+}
+
+/// Steps each channel of `current` by at most `STEP` towards `target`,
+/// one call per animation frame, so a color change reads as a crossfade.
+pub fn step_towards(current: [RGB8; PIXELS], target: [RGB8; PIXELS]) -> [RGB8; PIXELS] {
+    let mut next = current;
+    for (pixel, target) in next.iter_mut().zip(target.iter()) {
+        *pixel = step_channels(*pixel, *target);
+    }
+    next
+}
+
+fn step_channels(from: RGB8, to: RGB8) -> RGB8 {
+    RGB8 {
+        r: step_channel(from.r, to.r),
+        g: step_channel(from.g, to.g),
+        b: step_channel(from.b, to.b),
+    }
+}
+
+fn step_channel(from: u8, to: u8) -> u8 {
+    let from = i16::from(from);
+    let to = i16::from(to);
+    let delta = (to - from).clamp(-STEP, STEP);
+    (from + delta) as u8
+}
@@ -3,22 +3,38 @@
 #![no_main]
 #![no_std]
 
+mod display;
+mod ds3231;
+mod leds;
+
 use rregatta32 as _;
 
 #[rtic::app(device = stm32f1xx_hal::pac, dispatchers = [RTC, TIM2])]
 mod app {
+    use crate::display as screen;
+    use crate::ds3231::Ds3231;
+    use crate::leds;
     use defmt::{println, Format};
     use oorandom::Rand64;
+    use shared_bus::I2cProxy;
+    use smart_leds::RGB8;
     use stm32f1xx_hal::{
+        afio::AfioExt,
         flash::FlashExt,
         gpio::{
-            gpiob::{PB0, PB1, PB10, PB11, PB12, PB13},
-            GpioExt, Input, Output, PullDown, PushPull,
+            gpioa::{PA5, PA6, PA7},
+            gpiob::{PB0, PB12, PB13, PB6, PB7},
+            Alternate, Floating, GpioExt, Input, OpenDrain, PullDown, PushPull,
         },
+        i2c::{BlockingI2c, DutyCycle, Mode},
+        pac::{I2C1, SPI1, TIM3},
+        pwm::Channel,
         rcc::RccExt,
+        spi::{Spi, Spi1NoRemap},
+        timer::Tim3NoRemap,
     };
     use systick_monotonic::{
-        fugit::{Duration, RateExtU32, TimerInstantU64},
+        fugit::{Duration, HertzU32, RateExtU32, TimerInstantU64},
         ExtU64, Systick,
     };
 
@@ -26,21 +42,91 @@ mod app {
     #[monotonic(binds = SysTick, default = true)]
     type MonotonicTick = Systick<500>; // 500 Hz / 2 ms granularity
 
+    /// How long the start button must be held for the press to commit the
+    /// selected sequence and launch the race, rather than just cycling
+    /// the selection.
+    const LONG_PRESS_MS: u64 = 800;
+
+    /// How long after the starting signal the controller keeps listening
+    /// for recall taps before settling into idle.
+    const POST_START_WINDOW_MS: u64 = 10_000;
+
+    /// How long a second tap on the start button has to land after the
+    /// first one to count as a general- rather than individual-recall.
+    const RECALL_WINDOW_MS: u64 = 2_000;
+
+    /// How long the general-recall "all lights up" flash is held before
+    /// the re-armed controller's own target (e.g. Warmup's amber) is
+    /// allowed to show through, so the recall is actually visible instead
+    /// of being clobbered by the re-arm in the same tick.
+    const RECALL_FLASH_MS: u64 = 1_000;
+
     // shared resources between tasks
     // each resource can be passed to a task selectively
+    /// PWM handle for the horn/buzzer: TIM3 channel 3 on PB0, its default
+    /// (non-remapped) mapping, so the same pin now drives a square-wave
+    /// tone instead of a raw on/off level.
+    type Horn = stm32f1xx_hal::pwm::PwmHz<TIM3, Tim3NoRemap, ((), (), PB0<Alternate<PushPull>>, ())>;
+
+    /// The I2C1 bus on PB6 (SCL) / PB7 (SDA), shared between the OLED and
+    /// the DS3231 RTC via `shared-bus` proxies.
+    type I2cBus = BlockingI2c<I2C1, (PB6<Alternate<OpenDrain>>, PB7<Alternate<OpenDrain>>)>;
+    type I2cHandle = I2cProxy<'static, shared_bus::CortexMMutex<I2cBus>>;
+
+    /// SPI1 on its default (non-remapped) pins, MOSI (PA7) carrying the
+    /// WS2812 data line. SCK/MISO are unused by the strip but still owned
+    /// by the peripheral.
+    type LedSpi = Spi<
+        SPI1,
+        Spi1NoRemap,
+        (PA5<Alternate<PushPull>>, PA6<Input<Floating>>, PA7<Alternate<PushPull>>),
+        u8,
+    >;
+
     #[shared]
     struct Shared {
-        horn: PB0<Output<PushPull>>,    // 18
-        light1: PB11<Output<PushPull>>, // 22
-        light2: PB10<Output<PushPull>>, // 21
-        light3: PB1<Output<PushPull>>,  // 19
+        horn: Horn, // 18
+        leds: LedState,
         handel: Option<controller::MonotonicTick::SpawnHandle>,
+        countdown: CountdownTarget,
     }
 
     #[local]
     struct Local {
         start_button: PB12<Input<PullDown>>, // 25 5V/IO
         stop_button: PB13<Input<PullDown>>,  // 26 5V/IO
+        oled: screen::Oled<I2cHandle>,
+        display_rtc: Ds3231<I2cHandle>,
+        controller_rtc: Ds3231<I2cHandle>,
+        strip: leds::Strip<LedSpi>,
+        strip_frame: [RGB8; leds::PIXELS],
+    }
+
+    /// Target pixel colors for the flap-light strip, plus an optional
+    /// "flash" override fired alongside horn signals so the whole strip
+    /// flashes white regardless of the pattern underneath.
+    #[derive(Clone, Copy)]
+    pub struct LedState {
+        target: [RGB8; leds::PIXELS],
+        flash_until: Option<TimerInstantU64<500>>,
+    }
+
+    /// Live target for the OLED countdown: which sequence is running, the
+    /// state being counted down to, the instant it fires, and the warmup
+    /// duration once it's been rolled (so the display can show it
+    /// alongside the clock). `armed` is false while a sequence is still
+    /// being picked (no controller running yet), so the display can show
+    /// the pending selection instead of a stale countdown. `rtc_fault` is
+    /// set once the warmup seed falls back to a fixed value because the
+    /// DS3231 read failed.
+    #[derive(Clone, Copy)]
+    pub struct CountdownTarget {
+        sequence: Sequence,
+        state: State,
+        target: TimerInstantU64<500>,
+        warmup_secs: Option<u64>,
+        armed: bool,
+        rtc_fault: bool,
     }
 
     #[init]
@@ -48,10 +134,12 @@ mod app {
         let dp = cx.device; // device peripherals
         let mut flash = dp.FLASH.constrain();
         let rcc = dp.RCC.constrain();
-        // Acquire the GPIOB peripheral
+        let mut afio = dp.AFIO.constrain();
+        // Acquire the GPIOA/GPIOB peripherals
+        let mut gpioa = dp.GPIOA.split();
         let mut gpiob = dp.GPIOB.split();
 
-        let _clocks = rcc
+        let clocks = rcc
             .cfgr
             .use_hse(8.MHz())
             .sysclk(32.MHz())
@@ -64,34 +152,103 @@ mod app {
         let start_button = gpiob.pb12.into_pull_down_input(&mut gpiob.crh);
         let stop_button = gpiob.pb13.into_pull_down_input(&mut gpiob.crh);
 
-        let horn = gpiob.pb0.into_push_pull_output(&mut gpiob.crl);
-        let light1 = gpiob.pb11.into_push_pull_output(&mut gpiob.crh);
-        let light2 = gpiob.pb10.into_push_pull_output(&mut gpiob.crh);
-        let light3 = gpiob.pb1.into_push_pull_output(&mut gpiob.crl);
+        let horn_pin = gpiob.pb0.into_alternate_push_pull(&mut gpiob.crl);
+        let mut horn: Horn =
+            dp.TIM3
+                .pwm_hz(((), (), horn_pin, ()), &mut afio.mapr, 2.kHz(), &clocks);
+        horn.set_duty(Channel::C3, horn.get_max_duty() / 2);
+        horn.disable(Channel::C3);
+
+        // WS2812 flap-light strip on SPI1's MOSI (PA7), its default
+        // (non-remapped) pins
+        let sck = gpioa.pa5.into_alternate_push_pull(&mut gpioa.crl);
+        let miso = gpioa.pa6;
+        let mosi = gpioa.pa7.into_alternate_push_pull(&mut gpioa.crl);
+        let led_spi: LedSpi = Spi::spi1(
+            dp.SPI1,
+            (sck, miso, mosi),
+            &mut afio.mapr,
+            ws2812_spi::MODE,
+            3.MHz(),
+            clocks,
+        );
+        let strip = leds::init(led_spi);
+        let strip_frame = [RGB8::new(0, 0, 0); leds::PIXELS];
+
+        // I2C1 bus on PB6 (SCL) / PB7 (SDA), shared between the OLED and
+        // the DS3231 RTC
+        let scl = gpiob.pb6.into_alternate_open_drain(&mut gpiob.crl);
+        let sda = gpiob.pb7.into_alternate_open_drain(&mut gpiob.crl);
+        let i2c: I2cBus = BlockingI2c::i2c1(
+            dp.I2C1,
+            (scl, sda),
+            &mut afio.mapr,
+            Mode::Fast {
+                frequency: 400.kHz(),
+                duty_cycle: DutyCycle::Ratio2to1,
+            },
+            clocks,
+            1000,
+            10,
+            1000,
+            1000,
+        );
+        let bus = shared_bus::new_cortexm!(I2cBus = i2c).unwrap();
+
+        let oled = screen::init(bus.acquire_i2c());
+        let display_rtc = Ds3231::new(bus.acquire_i2c());
+        let controller_rtc = Ds3231::new(bus.acquire_i2c());
 
         reset_all::spawn().ok();
 
         // spawn task to periodically check button state
         poll_buttons::spawn(mono.now()).ok();
+        display::spawn_at(mono.now()).ok();
+        animate_leds::spawn().ok();
 
         (
             Shared {
                 handel: None,
                 horn,
-                light1,
-                light2,
-                light3,
+                leds: LedState {
+                    target: [RGB8::new(0, 0, 0); leds::PIXELS],
+                    flash_until: None,
+                },
+                countdown: CountdownTarget {
+                    sequence: Sequence::Westray,
+                    state: State::Warmup,
+                    target: mono.now(),
+                    warmup_secs: None,
+                    armed: false,
+                    rtc_fault: false,
+                },
             },
             Local {
                 start_button,
                 stop_button,
+                oled,
+                display_rtc,
+                controller_rtc,
+                strip,
+                strip_frame,
             },
             init::Monotonics(mono),
         )
     }
 
     /// periodic task to check buttons
-    #[task(priority=2, local = [count: u64 = 0, start_button, stop_button], shared = [handel])]
+    #[task(
+        priority=2,
+        local = [
+            start_button,
+            stop_button,
+            sequence: Sequence = Sequence::Westray,
+            pressed_at: Option<TimerInstantU64<500>> = None,
+            recall_taps: u8 = 0,
+            recall_first_tap: Option<TimerInstantU64<500>> = None,
+        ],
+        shared = [handel, countdown, leds],
+    )]
     fn poll_buttons(
         mut cx: poll_buttons::Context,
         instant: TimerInstantU64<500>,
@@ -99,11 +256,16 @@ mod app {
         let poll_buttons::LocalResources {
             start_button,
             stop_button,
-            count,
+            sequence,
+            pressed_at,
+            recall_taps,
+            recall_first_tap,
         } = cx.local;
 
-        // up the tick count by one
-        *count = count.wrapping_add(1);
+        let in_post_start = cx
+            .shared
+            .countdown
+            .lock(|countdown| matches!(countdown.state, State::PostStart));
 
         cx.shared.handel.lock(|handel| {
             if stop_button.is_high() {
@@ -112,176 +274,469 @@ mod app {
                     reset_all::spawn().ok();
                     if h.cancel().is_ok() {
                         defmt::println!("stopped");
-                        beep_horn::spawn_after(100.millis(), 300.millis(), 2)
+                        beep_horn::spawn_after(100.millis(), 300.millis(), TONE_HZ.Hz(), 2)
                             .ok();
                     } else {
                         defmt::println!("Something went wrong");
                     }
+                    // back to picking a sequence: drop the stale
+                    // countdown in favour of the selection display
+                    cx.shared.countdown.lock(|countdown| {
+                        countdown.state = State::Warmup;
+                        countdown.warmup_secs = None;
+                        countdown.armed = false;
+                        countdown.rtc_fault = false;
+                    });
+                    // and drop any recall tap still pending, so a stop
+                    // right after a tap doesn't fire a recall signal (or
+                    // worse, restart the race) once the window closes
+                    *recall_taps = 0;
+                    *recall_first_tap = None;
+                }
+            } else if start_button.is_high() {
+                if pressed_at.is_none() {
+                    *pressed_at = Some(instant);
+                }
+            } else if let Some(down_since) = pressed_at.take() {
+                // start button was just released
+                if handel.is_none() {
+                    // idle: a long hold commits the selection and
+                    // launches the race, a short tap just cycles the
+                    // sequence choice
+                    let held = instant
+                        .checked_duration_since(down_since)
+                        .unwrap_or(0.millis());
+                    if held.to_millis() >= LONG_PRESS_MS {
+                        let initial_state = if sequence.warmup_range().is_some() {
+                            State::Warmup
+                        } else {
+                            State::Step(0)
+                        };
+                        defmt::println!("Starting {:?}", sequence);
+                        *handel = controller::spawn_at(
+                            monotonics::now(),
+                            instant,
+                            *sequence,
+                            initial_state,
+                        )
+                        .ok();
+                    } else {
+                        *sequence = sequence.next();
+                        defmt::println!("Sequence -> {:?}", sequence);
+                        cx.shared.countdown.lock(|countdown| {
+                            countdown.sequence = *sequence;
+                        });
+                    }
+                } else if in_post_start {
+                    // a tap just after the start: record it, a second tap
+                    // landing within the recall window turns it into a
+                    // general rather than individual recall
+                    *recall_taps += 1;
+                    if recall_first_tap.is_none() {
+                        *recall_first_tap = Some(instant);
+                    }
                 }
-            } else if start_button.is_high() && handel.is_none() {
-                defmt::println!("spawning");
-                *handel = controller::spawn_at(
-                    monotonics::now(),
-                    instant,
-                    State::Warmup,
-                    *count,
-                )
-                .ok();
             }
         });
+
+        // resolve a pending recall once its window has closed
+        if let Some(first_tap) = *recall_first_tap {
+            let elapsed = instant
+                .checked_duration_since(first_tap)
+                .unwrap_or(0.millis());
+            if elapsed.to_millis() >= RECALL_WINDOW_MS {
+                let taps = *recall_taps;
+                *recall_first_tap = None;
+                *recall_taps = 0;
+
+                if taps >= 2 {
+                    defmt::println!("General recall");
+                    beep_horn::spawn(400.millis(), TONE_HZ.Hz(), 2).ok();
+                    // hold the white "all lights up" flash past the
+                    // immediate re-arm below, so it actually gets
+                    // rendered instead of being overwritten by the
+                    // re-armed controller's own target in the same tick
+                    cx.shared.leds.lock(|leds| {
+                        leds.flash_until = Some(instant + RECALL_FLASH_MS.millis());
+                    });
+                    cx.shared.handel.lock(|handel| {
+                        if let Some(h) = handel.take() {
+                            h.cancel().ok();
+                        }
+                        let initial_state = if sequence.warmup_range().is_some() {
+                            State::Warmup
+                        } else {
+                            State::Step(0)
+                        };
+                        *handel = controller::spawn_at(
+                            monotonics::now(),
+                            instant,
+                            *sequence,
+                            initial_state,
+                        )
+                        .ok();
+                    });
+                } else if taps == 1 {
+                    defmt::println!("Individual recall");
+                    beep_horn::spawn(1.secs(), TONE_HZ.Hz(), 1).ok();
+                }
+            }
+        }
+
         // Periodic check buttons every 50ms
         poll_buttons::spawn_at(instant, instant + 50.millis()).ok();
     }
 
-    /// State of the race timer each variant is used to perform a specific
-    /// operation and trigger next next task with a new state.
+    /// Position within the chosen `Sequence`: either waiting out the
+    /// random warmup pause, counting down through one of its steps, or
+    /// past the starting signal and still listening for recall taps.
     #[derive(Debug, Clone, Copy, Format)]
     pub enum State {
         Warmup,
-        Three,
-        Two,
-        One,
-        Start,
+        Step(u8),
+        PostStart,
+    }
+
+    /// A selectable start sequence. The operator cycles through these
+    /// with a short press on the start button, then commits with a long
+    /// press (see `poll_buttons`).
+    #[derive(Debug, Clone, Copy, Format)]
+    pub enum Sequence {
+        /// Club's own sequence: a horn for the warmup shot, a random
+        /// 30-60s pause, then a 3-minute start with flaps every minute.
+        Westray,
+        /// World Sailing RRS Rule 26, five-minute sequence.
+        Rrs5Min,
+        /// Three-minute dinghy variant of the RRS sequence.
+        Rrs3Min,
+    }
+
+    impl Sequence {
+        /// Cycles to the next sequence choice, wrapping around.
+        fn next(self) -> Self {
+            match self {
+                Sequence::Westray => Sequence::Rrs5Min,
+                Sequence::Rrs5Min => Sequence::Rrs3Min,
+                Sequence::Rrs3Min => Sequence::Westray,
+            }
+        }
+
+        /// The random warmup pause fired before the first step, if this
+        /// sequence has one.
+        fn warmup_range(self) -> Option<core::ops::Range<u64>> {
+            match self {
+                Sequence::Westray => Some(30..60),
+                Sequence::Rrs5Min | Sequence::Rrs3Min => None,
+            }
+        }
+
+        /// The ordered signals fired after the (optional) warmup pause,
+        /// ending with the starting signal itself.
+        fn steps(self) -> [SequenceStep; 4] {
+            use Light::*;
+            match self {
+                Sequence::Westray => [
+                    step("3", 1.minutes(), 1200.millis(), LONG_TONE_HZ, (On, On, On)),
+                    step("2", 1.minutes(), 400.millis(), TONE_HZ, (Off, On, On)),
+                    step("1", 1.minutes(), 400.millis(), TONE_HZ, (Off, Off, On)),
+                    step("Start", 0.millis(), 2000.millis(), TONE_HZ, (Off, Off, Off)),
+                ],
+                Sequence::Rrs5Min => [
+                    step("Warning", 1.minutes(), 400.millis(), TONE_HZ, (On, On, On)),
+                    step("Preparatory", 3.minutes(), 400.millis(), TONE_HZ, (Off, On, On)),
+                    step("1 Min", 1.minutes(), 1200.millis(), LONG_TONE_HZ, (Off, Off, On)),
+                    step("Start", 0.millis(), 400.millis(), TONE_HZ, (Off, Off, Off)),
+                ],
+                Sequence::Rrs3Min => [
+                    step("3 Min", 1.minutes(), 400.millis(), TONE_HZ, (On, On, On)),
+                    step("2 Min", 1.minutes(), 400.millis(), TONE_HZ, (Off, On, On)),
+                    step("1 Min", 1.minutes(), 400.millis(), TONE_HZ, (Off, Off, On)),
+                    step("Start", 0.millis(), 400.millis(), TONE_HZ, (Off, Off, Off)),
+                ],
+            }
+        }
+
+        /// Label for the OLED display.
+        fn label(self, state: State) -> &'static str {
+            match state {
+                State::Warmup => "Warmup",
+                State::Step(idx) => self.steps()[idx as usize].label,
+                State::PostStart => self.steps()[self.steps().len() - 1].label,
+            }
+        }
+
+        /// Name shown on the OLED while the operator is still picking a
+        /// sequence, before a race is armed.
+        fn name(self) -> &'static str {
+            match self {
+                Sequence::Westray => "Westray",
+                Sequence::Rrs5Min => "RRS 5-Min",
+                Sequence::Rrs3Min => "RRS 3-Min",
+            }
+        }
     }
 
-    #[task(priority=1, shared = [handel])]
+    /// A normal (short) sound signal; the long sounds (Westray's "3" and
+    /// the RRS five-minute "1 Min" signal) use `LONG_TONE_HZ` instead so
+    /// they're audibly distinct.
+    const TONE_HZ: u32 = 2_000;
+    /// Pitch for the long sound signals, distinguishable by ear from the
+    /// other (shorter) signals in a sequence.
+    const LONG_TONE_HZ: u32 = 3_000;
+
+    /// One signal in a `Sequence`: what to show, the horn/lights to fire,
+    /// and how long to wait after firing it before the next step fires.
+    #[derive(Clone, Copy)]
+    struct SequenceStep {
+        label: &'static str,
+        wait: Duration<u64, 1, 500>,
+        horn_ms: Duration<u64, 1, 500>,
+        tone_hz: u32,
+        lights: (Light, Light, Light),
+    }
+
+    fn step(
+        label: &'static str,
+        wait: Duration<u64, 1, 500>,
+        horn_ms: Duration<u64, 1, 500>,
+        tone_hz: u32,
+        lights: (Light, Light, Light),
+    ) -> SequenceStep {
+        SequenceStep {
+            label,
+            wait,
+            horn_ms,
+            tone_hz,
+            lights,
+        }
+    }
+
+    #[task(priority=1, shared = [handel, countdown], local = [controller_rtc])]
     fn controller(
         mut cx: controller::Context,
         instant: TimerInstantU64<500>,
+        sequence: Sequence,
         state: State,
-        seed: u64,
     ) {
-        use State::*;
-
-        defmt::println!("State {:?}", state);
-
-        // re-spawn self with given state and time (seconds from now)
-        let mut re_spawn = |state: State, duration: Duration<u64, 1, 500>| {
-            cx.shared.handel.lock(|handel| {
-                defmt::println!("spawning {:?}", state);
-                *handel = controller::spawn_at(
-                    instant + duration,
-                    instant + duration,
-                    state,
-                    seed,
-                )
-                .ok()
-            });
-        };
+        defmt::println!("{:?} {:?}", sequence, state);
+
+        // re-spawn self with the given state after `duration`, and
+        // publish the new target to the display task
+        let mut re_spawn =
+            |state: State, duration: Duration<u64, 1, 500>, warmup_secs, rtc_fault| {
+                let target = instant + duration;
+                cx.shared.handel.lock(|handel| {
+                    defmt::println!("spawning {:?}", state);
+                    *handel = controller::spawn_at(target, target, sequence, state).ok()
+                });
+                cx.shared.countdown.lock(|countdown| {
+                    *countdown = CountdownTarget {
+                        sequence,
+                        state,
+                        target,
+                        warmup_secs,
+                        armed: true,
+                        rtc_fault,
+                    }
+                });
+            };
 
         match state {
-            Warmup => {
-                // horn for 800ms once
-                beep_horn::spawn(800.millis(), 1).ok();
+            State::Warmup => {
+                // horn for the warmup shot, strip lit amber to mark the
+                // waiting period distinctly from the counting-down steps
+                beep_horn::spawn(800.millis(), TONE_HZ.Hz(), 1).ok();
+                set_lights::spawn(Light::Warmup, Light::Warmup, Light::Warmup).ok();
 
+                // the DS3231's seconds register mixed with its
+                // temperature reading makes a much less predictable seed
+                // than a free-running tick counter; fall back to a fixed
+                // seed if the RTC read fails, but make the degraded mode
+                // loud rather than silently predictable
+                let (seed, rtc_fault) = match cx.local.controller_rtc.entropy_seed() {
+                    Ok(seed) => (seed, false),
+                    Err(_) => {
+                        defmt::error!(
+                            "RTC entropy read failed, falling back to a fixed warmup seed"
+                        );
+                        (0x243F_6A88_85A3_08D3, true)
+                    }
+                };
                 defmt::println!("Seed {}", seed);
-                let random = Rand64::new(seed.into()).rand_range(30..60);
+                let random = Rand64::new(seed.into())
+                    .rand_range(sequence.warmup_range().unwrap_or(0..1));
                 defmt::println!("Warmup period: {}secs", random);
 
-                re_spawn(Three, random.secs());
-            }
-            Three => {
-                beep_horn::spawn(1200.millis(), 1).ok();
-                set_lights::spawn(Light::On, Light::On, Light::On).ok();
-                re_spawn(Two, 1.minutes());
-            }
-            Two => {
-                beep_horn::spawn(400.millis(), 1).ok();
-                set_lights::spawn(Light::Off, Light::On, Light::On).ok();
-                re_spawn(One, 1.minutes());
+                re_spawn(State::Step(0), random.secs(), Some(random), rtc_fault);
             }
-            One => {
-                beep_horn::spawn(400.millis(), 1).ok();
-                set_lights::spawn(Light::Off, Light::Off, Light::On).ok();
-                re_spawn(Start, 1.minutes());
+            State::Step(idx) => {
+                let step = sequence.steps()[idx as usize];
+                beep_horn::spawn(step.horn_ms, step.tone_hz.Hz(), 1).ok();
+                set_lights::spawn(step.lights.0, step.lights.1, step.lights.2).ok();
+
+                let rtc_fault = cx.shared.countdown.lock(|countdown| countdown.rtc_fault);
+                let next_idx = idx + 1;
+                if (next_idx as usize) < sequence.steps().len() {
+                    re_spawn(State::Step(next_idx), step.wait, None, rtc_fault);
+                } else {
+                    defmt::println!("Start !!!!!!!!!!!!!!");
+                    // stay alive a little longer so poll_buttons can turn
+                    // a start-button tap into a recall signal
+                    re_spawn(State::PostStart, POST_START_WINDOW_MS.millis(), None, rtc_fault);
+                }
             }
-            Start => {
-                beep_horn::spawn(2000.millis(), 1).ok();
-                set_lights::spawn(Light::Off, Light::Off, Light::Off).ok();
-                defmt::println!("Start !!!!!!!!!!!!!!");
+            State::PostStart => {
+                defmt::println!("Post-start recall window closed");
                 cx.shared.handel.lock(|handel| *handel = None);
+                // back to picking a sequence, so the display shows the
+                // selection rather than a stale countdown
+                cx.shared.countdown.lock(|countdown| countdown.armed = false);
             }
         }
     }
 
-    #[task(priority=1, shared = [horn, light1, light2, light3])]
+    /// Refreshes the OLED countdown display, re-spawning itself roughly
+    /// every 250ms so the clock keeps ticking between controller events.
+    #[task(priority=1, shared = [countdown], local = [oled, display_rtc])]
+    fn display(mut cx: display::Context) {
+        let countdown = cx.shared.countdown.lock(|countdown| *countdown);
+        let now = monotonics::now();
+        let remaining_secs = countdown
+            .target
+            .checked_duration_since(now)
+            .unwrap_or(0.millis())
+            .to_secs();
+
+        let wall_clock = cx
+            .local
+            .display_rtc
+            .time()
+            .ok()
+            .map(|t| (t.hours, t.minutes, t.seconds));
+
+        screen::render(
+            cx.local.oled,
+            screen::Countdown {
+                armed: countdown.armed,
+                sequence: countdown.sequence.name(),
+                state: countdown.sequence.label(countdown.state),
+                remaining_secs: remaining_secs as u32,
+                warmup_secs: countdown.warmup_secs,
+                rtc_fault: countdown.rtc_fault,
+                wall_clock,
+            },
+        );
+
+        display::spawn_after(250.millis()).ok();
+    }
+
+    #[task(priority=1, shared = [horn, leds])]
     fn reset_all(cx: reset_all::Context) {
-        let reset_all::SharedResources {
-            horn,
-            light1,
-            light2,
-            light3,
-        } = cx.shared;
-
-        (horn, light1, light2, light3).lock(|horn, light1, light2, light3| {
+        let reset_all::SharedResources { horn, leds } = cx.shared;
+
+        (horn, leds).lock(|horn, leds| {
             defmt::println!("Reset all");
-            horn.set_low();
-            light1.set_low();
-            light2.set_low();
-            light3.set_low();
+            horn.disable(Channel::C3);
+            leds.target = [RGB8::new(0, 0, 0); leds::PIXELS];
+            leds.flash_until = None;
         });
     }
 
-    #[derive(Format, Debug)]
+    #[derive(Format, Debug, Clone, Copy)]
     pub enum Light {
         On,
         Off,
+        /// Amber, used during the warmup wait so it reads as visibly
+        /// distinct from the On/Off flap pattern of the counted-down steps.
+        Warmup,
     }
 
-    /// set light status with a small delay in between
-    #[task(priority=1, shared = [light1, light2, light3])]
-    fn set_lights(cx: set_lights::Context, l1: Light, l2: Light, l3: Light) {
-        let set_lights::SharedResources {
-            light1,
-            light2,
-            light3,
-        } = cx.shared;
-
-        (light1, light2, light3).lock(|light1, light2, light3| {
-            defmt::println!("Setting lights {}--{}--{}", l1, l2, l3);
-
-            match l1 {
-                Light::On => light1.set_high(),
-                Light::Off => light1.set_low(),
-            }
-            match l2 {
-                Light::On => light2.set_high(),
-                Light::Off => light2.set_low(),
-            }
-            match l3 {
-                Light::On => light3.set_high(),
-                Light::Off => light3.set_low(),
+    impl Light {
+        fn color(self) -> RGB8 {
+            match self {
+                Light::On => RGB8::new(255, 255, 255),
+                Light::Off => RGB8::new(0, 0, 0),
+                Light::Warmup => RGB8::new(255, 140, 0),
             }
+        }
+    }
+
+    /// Sets the strip's target pattern; the `animate_leds` task crossfades
+    /// the physical pixels towards it rather than snapping instantly.
+    #[task(priority=1, shared = [leds])]
+    fn set_lights(mut cx: set_lights::Context, l1: Light, l2: Light, l3: Light) {
+        defmt::println!("Setting lights {}--{}--{}", l1, l2, l3);
+
+        cx.shared.leds.lock(|leds| {
+            leds.target = [l1.color(), l2.color(), l3.color()];
         });
     }
 
-    /// set horn state high for given milliseconds
-    #[task(priority=1, local = [is_high: bool = false], shared = [horn])]
+    /// sound the horn at `tone_hz` for the given duration
+    ///
+    /// `capacity = 2` so a second, independent caller (e.g. a recall
+    /// signal overlapping the next sequence's warmup shot) can still
+    /// queue its instance instead of silently losing the `spawn()`.
+    #[task(priority=1, capacity = 2, local = [is_high: bool = false], shared = [horn, leds])]
     fn beep_horn(
         mut cx: beep_horn::Context,
         duration: Duration<u64, 1, 500>,
+        tone_hz: HertzU32,
         times: i8,
     ) {
         if !*cx.local.is_high {
             *cx.local.is_high = true;
             cx.shared.horn.lock(|horn| {
                 println!("horn START");
-                horn.set_high();
+                horn.set_period(tone_hz);
+                horn.set_duty(Channel::C3, horn.get_max_duty() / 2);
+                horn.enable(Channel::C3);
             });
-            beep_horn::spawn_after(duration, duration, times - 1).ok();
+            // flash the whole strip white for the duration of the sound
+            // signal so it's visible from the water, not just audible;
+            // take the later of any already-pending flash deadline and
+            // this one, so an overlapping signal (e.g. a recall flash)
+            // can't get cut short by a shorter one starting afterwards
+            let now = monotonics::now();
+            let until = now + duration;
+            cx.shared.leds.lock(|leds| {
+                leds.flash_until = Some(leds.flash_until.map_or(until, |cur| cur.max(until)));
+            });
+            beep_horn::spawn_after(duration, duration, tone_hz, times - 1).ok();
         } else {
             *cx.local.is_high = false;
             cx.shared.horn.lock(|horn| {
                 println!("horn STOP");
-                horn.set_low();
+                horn.disable(Channel::C3);
             });
             // spawn again if times are left
             if times > 0 {
-                beep_horn::spawn_after(50.millis(), duration, times - 1).ok();
+                beep_horn::spawn_after(50.millis(), duration, tone_hz, times - 1).ok();
             }
         }
     }
 
+    /// Steps the strip one frame towards its current target (or a white
+    /// flash, while one is active) and re-spawns roughly every 20ms, so
+    /// color changes read as a gradual crossfade rather than a snap.
+    #[task(priority=1, local = [strip, strip_frame], shared = [leds])]
+    fn animate_leds(mut cx: animate_leds::Context) {
+        let now = monotonics::now();
+        let target = cx.shared.leds.lock(|leds| {
+            let flashing = leds.flash_until.is_some_and(|until| now < until);
+            if flashing {
+                [RGB8::new(255, 255, 255); leds::PIXELS]
+            } else {
+                leds.target
+            }
+        });
+
+        *cx.local.strip_frame = leds::step_towards(*cx.local.strip_frame, target);
+        leds::write(cx.local.strip, cx.local.strip_frame);
+
+        animate_leds::spawn_after(20.millis()).ok();
+    }
+
     #[idle]
     fn idle(_cx: idle::Context) -> ! {
         loop {